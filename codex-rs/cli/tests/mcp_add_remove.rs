@@ -28,9 +28,9 @@ fn add_and_remove_server_updates_global_config() -> Result<()> {
     assert_eq!(servers.len(), 1);
     let docs = servers.get("docs").expect("server should exist");
     assert!(docs.preset.is_none());
-    assert_eq!(docs.command, "echo");
-    assert_eq!(docs.args, vec!["hello".to_string()]);
-    assert!(docs.env.is_none());
+    assert_eq!(docs.command(), Some("echo"));
+    assert_eq!(docs.args(), ["hello".to_string()]);
+    assert!(docs.env().is_none());
 
     let mut remove_cmd = codex_command(codex_home.path())?;
     remove_cmd
@@ -78,7 +78,7 @@ fn add_with_env_preserves_key_order_and_values() -> Result<()> {
 
     let servers = load_global_mcp_servers(codex_home.path())?;
     let envy = servers.get("envy").expect("server should exist");
-    let env = envy.env.as_ref().expect("env should be present");
+    let env = envy.env().expect("env should be present");
 
     assert_eq!(env.len(), 2);
     assert_eq!(env.get("FOO"), Some(&"bar".to_string()));
@@ -100,13 +100,10 @@ fn add_with_preset_uses_builtin_defaults() -> Result<()> {
     let servers = load_global_mcp_servers(codex_home.path())?;
     let chrome = servers.get("chrome").expect("server should exist");
     assert_eq!(chrome.preset.as_deref(), Some("chrome_devtools"));
-    assert_eq!(chrome.command, "npx");
+    assert_eq!(chrome.command(), Some("npx"));
     assert_eq!(
-        chrome.args,
-        vec![
-            "chrome-devtools-mcp@latest".to_string(),
-            "--stdio".to_string()
-        ]
+        chrome.args(),
+        ["chrome-devtools-mcp@latest".to_string(), "--stdio".to_string()]
     );
     assert_eq!(chrome.startup_timeout_sec, Some(Duration::from_secs(45)));
     assert_eq!(chrome.tool_timeout_sec, Some(Duration::from_secs(120)));
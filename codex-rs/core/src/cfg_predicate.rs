@@ -0,0 +1,298 @@
+//! Parser and evaluator for the `cfg(...)` predicate syntax used by Cargo
+//! target specs, reused here so MCP server configs can be gated on the host
+//! platform (see [`crate::config_types::McpServerConfig::enable_if`]).
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// A parsed `cfg(...)` predicate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgPredicate {
+    All(Vec<CfgPredicate>),
+    Any(Vec<CfgPredicate>),
+    Not(Box<CfgPredicate>),
+    Equal(String, String),
+    Flag(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CfgPredicateError(String);
+
+impl fmt::Display for CfgPredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cfg(...) predicate: {}", self.0)
+    }
+}
+
+impl std::error::Error for CfgPredicateError {}
+
+/// Context a [`CfgPredicate`] is evaluated against. Built from
+/// [`std::env::consts`] via [`CfgContext::host`].
+#[derive(Debug, Clone, Default)]
+pub struct CfgContext {
+    keys: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl CfgContext {
+    /// Build a context describing the host this binary is running on.
+    pub fn host() -> Self {
+        let mut keys = vec![
+            ("target_os".to_string(), std::env::consts::OS.to_string()),
+            (
+                "target_family".to_string(),
+                std::env::consts::FAMILY.to_string(),
+            ),
+            (
+                "target_arch".to_string(),
+                std::env::consts::ARCH.to_string(),
+            ),
+        ];
+        let mut flags = Vec::new();
+        if cfg!(unix) {
+            flags.push("unix".to_string());
+        }
+        if cfg!(windows) {
+            flags.push("windows".to_string());
+        }
+        keys.retain(|(_, value)| !value.is_empty());
+        Self { keys, flags }
+    }
+
+    fn key_equals(&self, key: &str, value: &str) -> bool {
+        self.keys
+            .iter()
+            .any(|(k, v)| k == key && v == value)
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+/// Parse a `cfg(...)` predicate string, e.g. `cfg(target_os = "macos")` or
+/// `cfg(any(target_os = "linux", target_os = "macos"))`.
+pub fn parse(input: &str) -> Result<CfgPredicate, CfgPredicateError> {
+    let mut parser = Parser {
+        chars: input.char_indices().peekable(),
+        input,
+    };
+    parser.skip_whitespace();
+    parser.expect_ident("cfg")?;
+    parser.skip_whitespace();
+    parser.expect_char('(')?;
+    let predicate = parser.parse_predicate()?;
+    parser.skip_whitespace();
+    parser.expect_char(')')?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err(CfgPredicateError(format!(
+            "unexpected trailing input in '{input}'"
+        )));
+    }
+    Ok(predicate)
+}
+
+impl CfgPredicate {
+    /// Evaluate this predicate against `ctx`. Unknown keys compare false
+    /// rather than erroring so configs stay forward-compatible.
+    pub fn evaluate(&self, ctx: &CfgContext) -> bool {
+        match self {
+            CfgPredicate::All(children) => children.iter().all(|child| child.evaluate(ctx)),
+            CfgPredicate::Any(children) => children.iter().any(|child| child.evaluate(ctx)),
+            CfgPredicate::Not(child) => !child.evaluate(ctx),
+            CfgPredicate::Equal(key, value) => ctx.key_equals(key, value),
+            CfgPredicate::Flag(name) => ctx.has_flag(name),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.peek(), Some((_, c)) if c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn expect_char(&mut self, expected: char) -> Result<(), CfgPredicateError> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((pos, c)) => Err(CfgPredicateError(format!(
+                "expected '{expected}' at position {pos}, found '{c}'"
+            ))),
+            None => Err(CfgPredicateError(format!(
+                "expected '{expected}' but reached end of input"
+            ))),
+        }
+    }
+
+    fn expect_ident(&mut self, expected: &str) -> Result<(), CfgPredicateError> {
+        let ident = self.parse_ident()?;
+        if ident == expected {
+            Ok(())
+        } else {
+            Err(CfgPredicateError(format!(
+                "expected '{expected}', found '{ident}'"
+            )))
+        }
+    }
+
+    fn parse_ident(&mut self) -> Result<String, CfgPredicateError> {
+        let start = match self.chars.peek() {
+            Some((pos, c)) if c.is_alphabetic() || *c == '_' => *pos,
+            _ => return Err(CfgPredicateError("expected identifier".to_string())),
+        };
+        let mut end = start;
+        while let Some((pos, c)) = self.chars.peek() {
+            if c.is_alphanumeric() || *c == '_' {
+                end = pos + c.len_utf8();
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        Ok(self.input[start..end].to_string())
+    }
+
+    fn parse_string(&mut self) -> Result<String, CfgPredicateError> {
+        self.expect_char('"')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => break,
+                Some((_, c)) => value.push(c),
+                None => return Err(CfgPredicateError("unterminated string literal".to_string())),
+            }
+        }
+        Ok(value)
+    }
+
+    /// Parse a single predicate: `all(...)`, `any(...)`, `not(...)`,
+    /// `key = "value"`, or a bare flag identifier.
+    fn parse_predicate(&mut self) -> Result<CfgPredicate, CfgPredicateError> {
+        self.skip_whitespace();
+        let ident = self.parse_ident()?;
+        self.skip_whitespace();
+        match ident.as_str() {
+            "all" => Ok(CfgPredicate::All(self.parse_predicate_list()?)),
+            "any" => Ok(CfgPredicate::Any(self.parse_predicate_list()?)),
+            "not" => {
+                let mut children = self.parse_predicate_list()?;
+                if children.len() != 1 {
+                    return Err(CfgPredicateError(
+                        "not(...) takes exactly one predicate".to_string(),
+                    ));
+                }
+                Ok(CfgPredicate::Not(Box::new(children.remove(0))))
+            }
+            key => {
+                if self.peek_char() == Some('=') {
+                    self.chars.next();
+                    self.skip_whitespace();
+                    let value = self.parse_string()?;
+                    Ok(CfgPredicate::Equal(key.to_string(), value))
+                } else {
+                    Ok(CfgPredicate::Flag(key.to_string()))
+                }
+            }
+        }
+    }
+
+    /// Parse a parenthesized, comma-separated list of predicates, e.g. the
+    /// body of `all(...)`/`any(...)`/`not(...)`. Empty lists are allowed.
+    fn parse_predicate_list(&mut self) -> Result<Vec<CfgPredicate>, CfgPredicateError> {
+        self.expect_char('(')?;
+        let mut predicates = Vec::new();
+        self.skip_whitespace();
+        if self.peek_char() == Some(')') {
+            self.chars.next();
+            return Ok(predicates);
+        }
+        loop {
+            predicates.push(self.parse_predicate()?);
+            self.skip_whitespace();
+            match self.peek_char() {
+                Some(',') => {
+                    self.chars.next();
+                    self.skip_whitespace();
+                }
+                Some(')') => {
+                    self.chars.next();
+                    break;
+                }
+                _ => return Err(CfgPredicateError("expected ',' or ')'".to_string())),
+            }
+        }
+        Ok(predicates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx_with(os: &str) -> CfgContext {
+        CfgContext {
+            keys: vec![("target_os".to_string(), os.to_string())],
+            flags: vec!["unix".to_string()],
+        }
+    }
+
+    #[test]
+    fn parses_and_evaluates_simple_equal() {
+        let predicate = parse("cfg(target_os = \"macos\")").expect("valid predicate");
+        assert!(predicate.evaluate(&ctx_with("macos")));
+        assert!(!predicate.evaluate(&ctx_with("linux")));
+    }
+
+    #[test]
+    fn parses_any_and_all() {
+        let predicate = parse(
+            "cfg(any(target_os = \"linux\", target_os = \"macos\"))",
+        )
+        .expect("valid predicate");
+        assert!(predicate.evaluate(&ctx_with("linux")));
+        assert!(predicate.evaluate(&ctx_with("macos")));
+        assert!(!predicate.evaluate(&ctx_with("windows")));
+
+        let predicate = parse("cfg(all(unix, target_os = \"macos\"))").expect("valid predicate");
+        assert!(predicate.evaluate(&ctx_with("macos")));
+        assert!(!predicate.evaluate(&ctx_with("windows")));
+    }
+
+    #[test]
+    fn empty_all_and_any_evaluate_to_identity() {
+        assert!(parse("cfg(all())").unwrap().evaluate(&CfgContext::default()));
+        assert!(!parse("cfg(any())").unwrap().evaluate(&CfgContext::default()));
+    }
+
+    #[test]
+    fn not_negates_single_child() {
+        let predicate = parse("cfg(not(target_os = \"macos\"))").expect("valid predicate");
+        assert!(!predicate.evaluate(&ctx_with("macos")));
+        assert!(predicate.evaluate(&ctx_with("linux")));
+    }
+
+    #[test]
+    fn unknown_keys_compare_false() {
+        let predicate = parse("cfg(target_env = \"musl\")").expect("valid predicate");
+        assert!(!predicate.evaluate(&ctx_with("linux")));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(parse("target_os = \"macos\"").is_err());
+        assert!(parse("cfg(target_os = \"macos\"").is_err());
+        assert!(parse("cfg(not(unix, windows))").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Global config file loading, via [`crate::config_loader::ConfigBuilder`].
+//!
+//! This module only covers the `mcp_servers` table for now — the rest of
+//! [`Config`] lives outside this series and is not touched here.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::config_loader::ConfigBuilder;
+use crate::config_loader::ConfigLoadError;
+use crate::config_types::McpServerConfig;
+use crate::config_types::retain_mcp_servers_enabled_for_host;
+
+#[derive(Debug, Default, Deserialize)]
+struct GlobalConfigFile {
+    #[serde(default)]
+    mcp_servers: HashMap<String, McpServerConfig>,
+}
+
+/// Load the `mcp_servers` table from `<codex_home>/config.{toml,json,yaml,yml}`,
+/// via [`ConfigBuilder`] so a team can standardize on whichever format it
+/// prefers, layered under `CODEX_`-prefixed environment overrides. Entries
+/// whose [`McpServerConfig::enabled_for_host`] is `false` are dropped via
+/// [`retain_mcp_servers_enabled_for_host`] before the map is returned, so
+/// `enable_if` actually gates which servers callers ever see rather than
+/// merely being parse-validated. Resolves to an empty map if no config file
+/// exists, since it's optional.
+pub fn load_global_mcp_servers(
+    codex_home: &Path,
+) -> Result<HashMap<String, McpServerConfig>, ConfigLoadError> {
+    let file: GlobalConfigFile = ConfigBuilder::new()
+        .add_config_dir(codex_home)?
+        .add_env_prefix("CODEX_")
+        .build()?;
+    let mut servers = file.mcp_servers;
+    retain_mcp_servers_enabled_for_host(&mut servers);
+    Ok(servers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_servers_whose_enable_if_does_not_match_this_host() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            codex_home.path().join("config.toml"),
+            "[mcp_servers.keep]\ncommand = \"echo\"\n\n\
+             [mcp_servers.drop]\ncommand = \"echo\"\n\
+             enable_if = \"cfg(target_os = \\\"does-not-exist\\\")\"\n",
+        )
+        .expect("write config.toml");
+
+        let servers = load_global_mcp_servers(codex_home.path()).expect("config.toml should load");
+
+        assert_eq!(servers.len(), 1);
+        assert!(servers.contains_key("keep"));
+        assert!(!servers.contains_key("drop"));
+    }
+
+    #[test]
+    fn loads_mcp_servers_from_a_json_config_file_too() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            codex_home.path().join("config.json"),
+            r#"{"mcp_servers": {"docs": {"command": "npx", "args": ["docs-mcp"]}}}"#,
+        )
+        .expect("write config.json");
+
+        let servers = load_global_mcp_servers(codex_home.path()).expect("config.json should load");
+
+        let docs = servers.get("docs").expect("server should exist");
+        assert_eq!(docs.command(), Some("npx"));
+        assert_eq!(docs.args(), ["docs-mcp".to_string()]);
+    }
+
+    #[test]
+    fn missing_config_toml_yields_empty_map() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let servers = load_global_mcp_servers(codex_home.path()).expect("missing file is not an error");
+        assert!(servers.is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+//! Format-agnostic, layered configuration loading.
+//!
+//! Reads `config.toml`, `config.json`, and `config.yaml`/`.yml` and merges
+//! them in a defined precedence (builtin defaults < system < user <
+//! environment overrides) before performing a single `Deserialize` into the
+//! strongly-typed config structs in `crate::config`. This lets a team
+//! standardize on whichever format it prefers without the loader caring.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde_json::Map;
+use serde_json::Value;
+
+#[derive(Debug)]
+pub enum ConfigLoadError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    UnrecognizedFormat {
+        path: PathBuf,
+    },
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    Yaml {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+    Deserialize(serde_json::Error),
+}
+
+impl fmt::Display for ConfigLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigLoadError::Io { path, source } => {
+                write!(f, "failed to read config file {}: {source}", path.display())
+            }
+            ConfigLoadError::UnrecognizedFormat { path } => write!(
+                f,
+                "unrecognized config file extension for {} (expected .toml, .json, .yaml, or .yml)",
+                path.display()
+            ),
+            ConfigLoadError::Toml { path, source } => {
+                write!(f, "failed to parse {} as TOML: {source}", path.display())
+            }
+            ConfigLoadError::Json { path, source } => {
+                write!(f, "failed to parse {} as JSON: {source}", path.display())
+            }
+            ConfigLoadError::Yaml { path, source } => {
+                write!(f, "failed to parse {} as YAML: {source}", path.display())
+            }
+            ConfigLoadError::Deserialize(source) => {
+                write!(f, "failed to deserialize merged config: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigLoadError {}
+
+/// The file formats a config layer may be written in, inferred from its
+/// extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Some(Self::Toml),
+            Some("json") => Some(Self::Json),
+            Some("yaml") | Some("yml") => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    fn parse(self, path: &Path, contents: &str) -> Result<Value, ConfigLoadError> {
+        match self {
+            ConfigFormat::Toml => {
+                let value: toml::Value =
+                    toml::from_str(contents).map_err(|source| ConfigLoadError::Toml {
+                        path: path.to_path_buf(),
+                        source,
+                    })?;
+                Ok(toml_value_to_json(value))
+            }
+            ConfigFormat::Json => {
+                serde_json::from_str(contents).map_err(|source| ConfigLoadError::Json {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+            ConfigFormat::Yaml => {
+                let value: serde_yaml::Value =
+                    serde_yaml::from_str(contents).map_err(|source| ConfigLoadError::Yaml {
+                        path: path.to_path_buf(),
+                        source,
+                    })?;
+                Ok(yaml_value_to_json(value))
+            }
+        }
+    }
+}
+
+fn toml_value_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Number(i.into()),
+        toml::Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(values) => {
+            Value::Array(values.into_iter().map(toml_value_to_json).collect())
+        }
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_value_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn yaml_value_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::Number(i.into())
+            } else if let Some(f) = n.as_f64() {
+                serde_json::Number::from_f64(f)
+                    .map(Value::Number)
+                    .unwrap_or(Value::Null)
+            } else {
+                Value::Null
+            }
+        }
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(values) => {
+            Value::Array(values.into_iter().map(yaml_value_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(mapping) => Value::Object(
+            mapping
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    key.as_str()
+                        .map(|key| (key.to_string(), yaml_value_to_json(value)))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => yaml_value_to_json(tagged.value),
+    }
+}
+
+/// Deep-merge `overlay` into `base`: objects are merged key-by-key
+/// (recursively), with `overlay` winning on conflicts; every other value
+/// type (including arrays) is replaced wholesale by `overlay`.
+fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base_map), Value::Object(overlay_map)) => {
+            for (key, overlay_value) in overlay_map {
+                match base_map.get_mut(&key) {
+                    Some(base_value) => deep_merge(base_value, overlay_value),
+                    None => {
+                        base_map.insert(key, overlay_value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Build nested JSON from `CODEX_<SECTION>_<KEY>`-style environment
+/// variable names, e.g. `CODEX_HISTORY_PERSISTENCE=save-all` becomes
+/// `{"history": {"persistence": "save-all"}}`. Values are parsed as JSON
+/// when possible (so `CODEX_TUI_NOTIFICATIONS=true` yields a bool) and
+/// fall back to a plain string otherwise.
+fn env_layer_from_prefix(prefix: &str, vars: impl Iterator<Item = (String, String)>) -> Value {
+    let mut root = Map::new();
+    for (key, raw_value) in vars {
+        let Some(rest) = key.strip_prefix(prefix) else {
+            continue;
+        };
+        let segments: Vec<String> = rest
+            .trim_start_matches('_')
+            .split('_')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.to_lowercase())
+            .collect();
+        if segments.is_empty() {
+            continue;
+        }
+
+        let value = serde_json::from_str(&raw_value).unwrap_or(Value::String(raw_value));
+
+        let mut cursor = &mut root;
+        for segment in &segments[..segments.len() - 1] {
+            let slot = cursor
+                .entry(segment.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !slot.is_object() {
+                *slot = Value::Object(Map::new());
+            }
+            cursor = slot
+                .as_object_mut()
+                .expect("intermediate env layer segments are always objects");
+        }
+        cursor.insert(segments[segments.len() - 1].clone(), value);
+    }
+    Value::Object(root)
+}
+
+/// Collects typed config sources, merges them into a single JSON tree, and
+/// deserializes the result into a strongly-typed config struct.
+///
+/// Layers are applied in the order they're added; later layers win on
+/// conflicting keys. The conventional ordering is builtin defaults, then
+/// system config, then user config, then environment overrides.
+#[derive(Debug)]
+pub struct ConfigBuilder {
+    merged: Value,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self {
+            merged: Value::Object(Map::new()),
+        }
+    }
+
+    /// Merge `value` in as the next-highest-precedence layer.
+    pub fn add_value(mut self, value: Value) -> Self {
+        deep_merge(&mut self.merged, value);
+        self
+    }
+
+    /// Read, parse (by extension: `.toml`, `.json`, `.yaml`/`.yml`), and
+    /// merge in a config file. Missing files are silently skipped so
+    /// optional layers (e.g. a system-wide config) don't have to exist.
+    pub fn add_file(self, path: &Path) -> Result<Self, ConfigLoadError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(source) if source.kind() == std::io::ErrorKind::NotFound => return Ok(self),
+            Err(source) => {
+                return Err(ConfigLoadError::Io {
+                    path: path.to_path_buf(),
+                    source,
+                });
+            }
+        };
+        let format = ConfigFormat::from_path(path).ok_or_else(|| ConfigLoadError::UnrecognizedFormat {
+            path: path.to_path_buf(),
+        })?;
+        let value = format.parse(path, &contents)?;
+        Ok(self.add_value(value))
+    }
+
+    /// Merge in the first of `config.toml`, `config.json`, `config.yaml`,
+    /// `config.yml` that exists under `dir`. A no-op if none exist.
+    pub fn add_config_dir(mut self, dir: &Path) -> Result<Self, ConfigLoadError> {
+        for extension in ["toml", "json", "yaml", "yml"] {
+            let path = dir.join("config").with_extension(extension);
+            if path.exists() {
+                self = self.add_file(&path)?;
+                break;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Merge in an environment-variable layer: every `{prefix}<SECTION>_<KEY>`
+    /// variable maps to the nested key `section.key`, e.g. `CODEX_HISTORY_PERSISTENCE`
+    /// overrides `history.persistence`.
+    pub fn add_env_prefix(self, prefix: &str) -> Self {
+        let vars = std::env::vars();
+        self.add_value(env_layer_from_prefix(prefix, vars))
+    }
+
+    /// Deserialize the merged layers into `T`.
+    pub fn build<T: DeserializeOwned>(self) -> Result<T, ConfigLoadError> {
+        serde_json::from_value(self.merged).map_err(ConfigLoadError::Deserialize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    struct ExampleConfig {
+        #[serde(default)]
+        history: ExampleHistory,
+        #[serde(default)]
+        tui: ExampleTui,
+    }
+
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    struct ExampleHistory {
+        #[serde(default)]
+        persistence: Option<String>,
+    }
+
+    #[derive(Debug, Default, Deserialize, PartialEq)]
+    struct ExampleTui {
+        #[serde(default)]
+        notifications: Option<bool>,
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let config: ExampleConfig = ConfigBuilder::new()
+            .add_value(serde_json::json!({"history": {"persistence": "save-all"}}))
+            .add_value(serde_json::json!({"history": {"persistence": "none"}}))
+            .build()
+            .expect("valid merged config");
+
+        assert_eq!(config.history.persistence.as_deref(), Some("none"));
+    }
+
+    #[test]
+    fn objects_merge_key_by_key_but_arrays_replace() {
+        let mut base = serde_json::json!({"a": {"x": 1, "y": 2}, "list": [1, 2]});
+        deep_merge(&mut base, serde_json::json!({"a": {"y": 3}, "list": [9]}));
+        assert_eq!(base, serde_json::json!({"a": {"x": 1, "y": 3}, "list": [9]}));
+    }
+
+    #[test]
+    fn env_layer_builds_nested_keys_and_parses_values() {
+        let vars = vec![
+            ("CODEX_HISTORY_PERSISTENCE".to_string(), "none".to_string()),
+            ("CODEX_TUI_NOTIFICATIONS".to_string(), "true".to_string()),
+            ("UNRELATED_VAR".to_string(), "ignored".to_string()),
+        ];
+        let layer = env_layer_from_prefix("CODEX_", vars.into_iter());
+        assert_eq!(
+            layer,
+            serde_json::json!({
+                "history": {"persistence": "none"},
+                "tui": {"notifications": true},
+            })
+        );
+    }
+
+    #[test]
+    fn toml_and_yaml_layers_parse_to_equivalent_json() {
+        let toml_value = ConfigFormat::Toml
+            .parse(Path::new("config.toml"), "history.persistence = \"none\"\n")
+            .expect("valid toml");
+        let yaml_value = ConfigFormat::Yaml
+            .parse(Path::new("config.yaml"), "history:\n  persistence: none\n")
+            .expect("valid yaml");
+        assert_eq!(toml_value, yaml_value);
+    }
+}
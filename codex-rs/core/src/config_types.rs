@@ -13,19 +13,42 @@ use serde::Deserializer;
 use serde::Serialize;
 use serde::de::Error as SerdeError;
 
+use crate::cfg_predicate::CfgContext;
+use crate::cfg_predicate::parse as parse_cfg_predicate;
 use crate::mcp_presets::find_mcp_server_preset;
+
+/// How an MCP server is reached: a locally spawned stdio child process, or
+/// a remote server speaking the MCP Streamable HTTP / SSE transport.
+/// Selected during deserialization by the presence of `url` vs `command`
+/// (see [`McpServerConfig`]'s `Deserialize` impl); the flat TOML keys are
+/// preserved for backward compatibility via `#[serde(flatten)]`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum McpTransport {
+    Stdio {
+        command: String,
+
+        #[serde(default)]
+        args: Vec<String>,
+
+        #[serde(default)]
+        env: Option<HashMap<String, String>>,
+    },
+    Http {
+        url: String,
+
+        #[serde(default)]
+        headers: Option<HashMap<String, String>>,
+    },
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preset: Option<String>,
 
-    pub command: String,
-
-    #[serde(default)]
-    pub args: Vec<String>,
-
-    #[serde(default)]
-    pub env: Option<HashMap<String, String>>,
+    #[serde(flatten)]
+    pub transport: McpTransport,
 
     /// Startup timeout in seconds for initializing MCP server & initially listing tools.
     #[serde(
@@ -38,6 +61,77 @@ pub struct McpServerConfig {
     /// Default timeout for MCP tool calls initiated via this server.
     #[serde(default, with = "option_duration_secs")]
     pub tool_timeout_sec: Option<Duration>,
+
+    /// `cfg(...)` predicate (Cargo target-spec syntax) gating whether this
+    /// server is activated on the current host. `None` means always enabled.
+    /// Parsed and validated at load time so typos surface immediately; see
+    /// [`Self::enabled_for_host`] for evaluation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enable_if: Option<String>,
+}
+
+impl McpServerConfig {
+    /// Whether this server should be activated on the current host,
+    /// evaluating [`Self::enable_if`] (if any) against [`CfgContext::host`].
+    /// Servers with no `enable_if` are always enabled.
+    pub fn enabled_for_host(&self) -> bool {
+        match &self.enable_if {
+            Some(predicate) => match parse_cfg_predicate(predicate) {
+                Ok(predicate) => predicate.evaluate(&CfgContext::host()),
+                Err(_) => false,
+            },
+            None => true,
+        }
+    }
+
+    /// The command to launch, for [`McpTransport::Stdio`] servers.
+    pub fn command(&self) -> Option<&str> {
+        match &self.transport {
+            McpTransport::Stdio { command, .. } => Some(command),
+            McpTransport::Http { .. } => None,
+        }
+    }
+
+    /// Arguments appended to [`Self::command`]. Empty for HTTP servers.
+    pub fn args(&self) -> &[String] {
+        match &self.transport {
+            McpTransport::Stdio { args, .. } => args,
+            McpTransport::Http { .. } => &[],
+        }
+    }
+
+    /// Environment variables for [`McpTransport::Stdio`] servers.
+    pub fn env(&self) -> Option<&HashMap<String, String>> {
+        match &self.transport {
+            McpTransport::Stdio { env, .. } => env.as_ref(),
+            McpTransport::Http { .. } => None,
+        }
+    }
+
+    /// The endpoint to connect to, for [`McpTransport::Http`] servers.
+    pub fn url(&self) -> Option<&str> {
+        match &self.transport {
+            McpTransport::Http { url, .. } => Some(url),
+            McpTransport::Stdio { .. } => None,
+        }
+    }
+
+    /// Headers sent with every request, for [`McpTransport::Http`] servers.
+    pub fn headers(&self) -> Option<&HashMap<String, String>> {
+        match &self.transport {
+            McpTransport::Http { headers, .. } => headers.as_ref(),
+            McpTransport::Stdio { .. } => None,
+        }
+    }
+}
+
+/// Drop every server whose [`McpServerConfig::enabled_for_host`] is `false`.
+/// Config loaders (e.g. `load_global_mcp_servers`) must call this right
+/// after parsing the `mcp_servers` table so `enable_if` actually gates which
+/// servers the rest of the program ever sees, rather than leaving each
+/// caller to remember to check `enabled_for_host()` individually.
+pub fn retain_mcp_servers_enabled_for_host(servers: &mut HashMap<String, McpServerConfig>) {
+    servers.retain(|_, server| server.enabled_for_host());
 }
 
 impl<'de> Deserialize<'de> for McpServerConfig {
@@ -56,11 +150,17 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             #[serde(default)]
             env: Option<HashMap<String, String>>,
             #[serde(default)]
+            url: Option<String>,
+            #[serde(default)]
+            headers: Option<HashMap<String, String>>,
+            #[serde(default)]
             startup_timeout_sec: Option<f64>,
             #[serde(default)]
             startup_timeout_ms: Option<u64>,
             #[serde(default, with = "option_duration_secs")]
             tool_timeout_sec: Option<Duration>,
+            #[serde(default)]
+            enable_if: Option<String>,
         }
 
         let raw = RawMcpServerConfig::deserialize(deserializer)?;
@@ -70,11 +170,24 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             command,
             args,
             env,
+            url,
+            headers,
             startup_timeout_sec: raw_startup_timeout_sec,
             startup_timeout_ms,
             tool_timeout_sec,
+            enable_if,
         } = raw;
 
+        if let Some(predicate) = enable_if.as_deref() {
+            parse_cfg_predicate(predicate).map_err(SerdeError::custom)?;
+        }
+
+        if command.is_some() && url.is_some() {
+            return Err(SerdeError::custom(
+                "MCP server config cannot specify both `command` and `url`",
+            ));
+        }
+
         let startup_timeout_override = match (raw_startup_timeout_sec, startup_timeout_ms) {
             (Some(sec), _) => {
                 let duration = Duration::try_from_secs_f64(sec).map_err(SerdeError::custom)?;
@@ -94,38 +207,54 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             None => None,
         };
 
-        let mut command_value = if let Some(cfg) = preset_config.as_ref() {
-            cfg.command.clone()
+        let transport = if let Some(url) = url {
+            McpTransport::Http { url, headers }
         } else {
-            command
-                .clone()
-                .ok_or_else(|| SerdeError::missing_field("command"))?
-        };
-        if let Some(cmd) = command {
-            command_value = cmd;
-        }
+            let mut command_value = if let Some(cfg) = preset_config.as_ref() {
+                cfg.command()
+                    .map(str::to_string)
+                    .ok_or_else(|| SerdeError::missing_field("command"))?
+            } else {
+                command
+                    .clone()
+                    .ok_or_else(|| SerdeError::missing_field("command"))?
+            };
+            if let Some(cmd) = command {
+                command_value = cmd;
+            }
 
-        let mut args_value = preset_config
-            .as_ref()
-            .map(|cfg| cfg.args.clone())
-            .unwrap_or_default();
-        if !args.is_empty() {
-            args_value = args;
-        }
+            let mut args_value = preset_config
+                .as_ref()
+                .map(|cfg| cfg.args().to_vec())
+                .unwrap_or_default();
+            if !args.is_empty() {
+                args_value = args;
+            }
 
-        let mut env_value = preset_config
-            .as_ref()
-            .and_then(|cfg| cfg.env.clone())
-            .unwrap_or_default();
-        if let Some(env_override) = env {
-            if env_value.is_empty() {
-                env_value = env_override;
-            } else {
-                for (key, value) in env_override {
-                    env_value.insert(key, value);
+            let mut env_value = preset_config
+                .as_ref()
+                .and_then(|cfg| cfg.env().cloned())
+                .unwrap_or_default();
+            if let Some(env_override) = env {
+                if env_value.is_empty() {
+                    env_value = env_override;
+                } else {
+                    for (key, value) in env_override {
+                        env_value.insert(key, value);
+                    }
                 }
             }
-        }
+
+            McpTransport::Stdio {
+                command: command_value,
+                args: args_value,
+                env: if env_value.is_empty() {
+                    None
+                } else {
+                    Some(env_value)
+                },
+            }
+        };
 
         let mut startup_timeout_value = preset_config
             .as_ref()
@@ -141,15 +270,10 @@ impl<'de> Deserialize<'de> for McpServerConfig {
 
         Ok(Self {
             preset,
-            command: command_value,
-            args: args_value,
-            env: if env_value.is_empty() {
-                None
-            } else {
-                Some(env_value)
-            },
+            transport,
             startup_timeout_sec: startup_timeout_value,
             tool_timeout_sec: tool_timeout_value,
+            enable_if,
         })
     }
 }
@@ -254,18 +378,79 @@ pub struct Tui {
     pub notifications: Notifications,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct SandboxWorkspaceWrite {
-    #[serde(default)]
     pub writable_roots: Vec<PathBuf>,
-    #[serde(default)]
     pub network_access: bool,
-    #[serde(default)]
     pub exclude_tmpdir_env_var: bool,
-    #[serde(default)]
     pub exclude_slash_tmp: bool,
+
+    /// Linux capabilities to drop from commands spawned under this sandbox.
+    /// Accepts `CAP_*` names or the `"all"` shorthand for dropping
+    /// everything not named in `keep_capabilities`. No-op on non-Linux
+    /// hosts so configs remain portable across a mixed fleet.
+    pub drop_capabilities: Vec<String>,
+
+    /// Linux capabilities to retain even when `drop_capabilities` contains
+    /// `"all"`.
+    pub keep_capabilities: Vec<String>,
 }
 
+impl<'de> Deserialize<'de> for SandboxWorkspaceWrite {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawSandboxWorkspaceWrite {
+            #[serde(default)]
+            writable_roots: Vec<PathBuf>,
+            #[serde(default)]
+            network_access: bool,
+            #[serde(default)]
+            exclude_tmpdir_env_var: bool,
+            #[serde(default)]
+            exclude_slash_tmp: bool,
+            #[serde(default)]
+            drop_capabilities: Vec<String>,
+            #[serde(default)]
+            keep_capabilities: Vec<String>,
+        }
+
+        let raw = RawSandboxWorkspaceWrite::deserialize(deserializer)?;
+
+        for name in raw.drop_capabilities.iter().chain(&raw.keep_capabilities) {
+            crate::linux_capabilities::validate_capability_name(name)
+                .map_err(SerdeError::custom)?;
+        }
+
+        Ok(Self {
+            writable_roots: raw.writable_roots,
+            network_access: raw.network_access,
+            exclude_tmpdir_env_var: raw.exclude_tmpdir_env_var,
+            exclude_slash_tmp: raw.exclude_slash_tmp,
+            drop_capabilities: raw.drop_capabilities,
+            keep_capabilities: raw.keep_capabilities,
+        })
+    }
+}
+
+impl SandboxWorkspaceWrite {
+    /// Apply `drop_capabilities`/`keep_capabilities` to the current process.
+    /// Spawn call sites for commands run under this sandbox must invoke this
+    /// post-fork/pre-exec so the capability policy actually takes effect.
+    /// Does nothing when `drop_capabilities` is empty; otherwise, on non-Linux
+    /// hosts, it only logs the warning in [`crate::linux_capabilities::apply`].
+    pub fn apply_capabilities(&self) {
+        crate::linux_capabilities::apply(&self.drop_capabilities, &self.keep_capabilities);
+    }
+}
+
+// `codex_protocol::mcp_protocol::SandboxSettings` is the over-the-wire
+// description of this sandbox sent to MCP clients; it does not yet have
+// `drop_capabilities`/`keep_capabilities` fields, so capability settings
+// stay core-side (applied via `apply_capabilities` above) until that
+// protocol type is extended to carry them.
 impl From<SandboxWorkspaceWrite> for codex_protocol::mcp_protocol::SandboxSettings {
     fn from(sandbox_workspace_write: SandboxWorkspaceWrite) -> Self {
         Self {
@@ -309,17 +494,29 @@ pub struct ShellEnvironmentPolicyToml {
     pub include_only: Option<Vec<String>>,
 
     pub experimental_use_profile: Option<bool>,
+
+    /// Dotenv-style files whose `KEY=value` entries are loaded as a layer
+    /// between `inherit` and `set`, e.g. for keeping secrets out of
+    /// `config.toml`.
+    #[serde(default)]
+    pub env_files: Vec<PathBuf>,
 }
 
 pub type EnvironmentVariablePattern = WildMatchPattern<'*', '?'>;
 
-/// Deriving the `env` based on this policy works as follows:
+/// Deriving the `env` based on this policy works as follows (see
+/// [`crate::shell_environment::derive_shell_environment`] for the
+/// implementation):
 /// 1. Create an initial map based on the `inherit` policy.
-/// 2. If `ignore_default_excludes` is false, filter the map using the default
+/// 2. Merge each `env_files` entry, in order, into the map.
+/// 3. If `ignore_default_excludes` is false, filter the map using the default
 ///    exclude pattern(s), which are: `"*KEY*"` and `"*TOKEN*"`.
-/// 3. If `exclude` is not empty, filter the map using the provided patterns.
-/// 4. Insert any entries from `r#set` into the map.
-/// 5. If non-empty, filter the map using the `include_only` patterns.
+/// 4. If `exclude` is not empty, filter the map using the provided patterns.
+/// 5. Insert entries from `r#set` into the map, expanding `${NAME}`
+///    references against the map as built so far (steps 1-4); unknown
+///    references expand to the empty string, and `$${` is a literal `${`
+///    escape.
+/// 6. If non-empty, filter the map using the `include_only` patterns.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct ShellEnvironmentPolicy {
     /// Starting point when building the environment.
@@ -332,7 +529,8 @@ pub struct ShellEnvironmentPolicy {
     /// Environment variable names to exclude from the environment.
     pub exclude: Vec<EnvironmentVariablePattern>,
 
-    /// (key, value) pairs to insert in the environment.
+    /// (key, value) pairs to insert in the environment. Values may reference
+    /// `${NAME}` to interpolate another variable already in the map.
     pub r#set: HashMap<String, String>,
 
     /// Environment variable names to retain in the environment.
@@ -340,6 +538,10 @@ pub struct ShellEnvironmentPolicy {
 
     /// If true, the shell profile will be used to run the command.
     pub use_profile: bool,
+
+    /// Dotenv-style files merged into the environment between `inherit` and
+    /// the default excludes.
+    pub env_files: Vec<PathBuf>,
 }
 
 impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
@@ -369,6 +571,7 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
             r#set,
             include_only,
             use_profile,
+            env_files: toml.env_files,
         }
     }
 }
@@ -393,13 +596,10 @@ mod tests {
             toml::from_str("preset = \"chrome_devtools\"\n").expect("valid preset config");
 
         assert_eq!(cfg.preset.as_deref(), Some("chrome_devtools"));
-        assert_eq!(cfg.command, "npx");
+        assert_eq!(cfg.command(), Some("npx"));
         assert_eq!(
-            cfg.args,
-            vec![
-                "chrome-devtools-mcp@latest".to_string(),
-                "--stdio".to_string()
-            ]
+            cfg.args(),
+            ["chrome-devtools-mcp@latest".to_string(), "--stdio".to_string()]
         );
         assert_eq!(cfg.startup_timeout_sec, Some(Duration::from_secs(45)));
         assert_eq!(cfg.tool_timeout_sec, Some(Duration::from_secs(120)));
@@ -413,12 +613,120 @@ mod tests {
         .expect("valid override config");
 
         assert_eq!(cfg.preset.as_deref(), Some("chrome_devtools"));
-        assert_eq!(cfg.command, "/custom/bin");
-        assert_eq!(cfg.args, vec!["--foo".to_string()]);
+        assert_eq!(cfg.command(), Some("/custom/bin"));
+        assert_eq!(cfg.args(), ["--foo".to_string()]);
         let mut expected_env = HashMap::new();
         expected_env.insert("CUSTOM".to_string(), "1".to_string());
-        assert_eq!(cfg.env, Some(expected_env));
+        assert_eq!(cfg.env(), Some(&expected_env));
         assert_eq!(cfg.startup_timeout_sec, Some(Duration::from_secs(5)));
         assert_eq!(cfg.tool_timeout_sec, Some(Duration::from_secs(7)));
     }
+
+    #[test]
+    fn http_transport_parses_from_url() {
+        let cfg: McpServerConfig = toml::from_str(
+            "url = \"https://example.com/mcp\"\nheaders = { Authorization = \"Bearer token\" }\n",
+        )
+        .expect("valid http config");
+
+        assert_eq!(cfg.command(), None);
+        assert_eq!(cfg.url(), Some("https://example.com/mcp"));
+        let mut expected_headers = HashMap::new();
+        expected_headers.insert("Authorization".to_string(), "Bearer token".to_string());
+        assert_eq!(cfg.headers(), Some(&expected_headers));
+    }
+
+    #[test]
+    fn rejects_both_command_and_url() {
+        let err = toml::from_str::<McpServerConfig>(
+            "command = \"echo\"\nurl = \"https://example.com/mcp\"\n",
+        )
+        .expect_err("command and url are mutually exclusive");
+        assert!(err.to_string().contains("cannot specify both"));
+    }
+
+    #[test]
+    fn enable_if_gates_server_by_host() {
+        let cfg: McpServerConfig = toml::from_str(
+            "command = \"echo\"\nenable_if = \"cfg(target_os = \\\"does-not-exist\\\")\"\n",
+        )
+        .expect("valid config");
+
+        assert_eq!(
+            cfg.enable_if.as_deref(),
+            Some("cfg(target_os = \"does-not-exist\")")
+        );
+        assert!(!cfg.enabled_for_host());
+    }
+
+    #[test]
+    fn retain_enabled_for_host_drops_non_matching_servers() {
+        let matching: McpServerConfig = toml::from_str("command = \"echo\"\n").expect("valid config");
+        let non_matching: McpServerConfig = toml::from_str(
+            "command = \"echo\"\nenable_if = \"cfg(target_os = \\\"does-not-exist\\\")\"\n",
+        )
+        .expect("valid config");
+
+        let mut servers = HashMap::new();
+        servers.insert("keep".to_string(), matching);
+        servers.insert("drop".to_string(), non_matching);
+
+        retain_mcp_servers_enabled_for_host(&mut servers);
+
+        assert_eq!(servers.len(), 1);
+        assert!(servers.contains_key("keep"));
+        assert!(!servers.contains_key("drop"));
+    }
+
+    #[test]
+    fn enable_if_rejects_malformed_predicate_at_load_time() {
+        let err = toml::from_str::<McpServerConfig>(
+            "command = \"echo\"\nenable_if = \"target_os = \\\"macos\\\"\"\n",
+        )
+        .expect_err("malformed predicate should fail to deserialize");
+        assert!(err.to_string().contains("invalid cfg(...) predicate"));
+    }
+
+    #[test]
+    fn sandbox_workspace_write_accepts_known_capabilities() {
+        let sandbox: SandboxWorkspaceWrite = toml::from_str(
+            "drop_capabilities = [\"all\"]\nkeep_capabilities = [\"CAP_NET_BIND_SERVICE\"]\n",
+        )
+        .expect("valid sandbox config");
+
+        assert_eq!(sandbox.drop_capabilities, vec!["all".to_string()]);
+        assert_eq!(
+            sandbox.keep_capabilities,
+            vec!["CAP_NET_BIND_SERVICE".to_string()]
+        );
+    }
+
+    #[test]
+    fn sandbox_workspace_write_rejects_unknown_capability() {
+        let err = toml::from_str::<SandboxWorkspaceWrite>("drop_capabilities = [\"CAP_BOGUS\"]\n")
+            .expect_err("unknown capability should fail to deserialize");
+        assert!(err.to_string().contains("CAP_BOGUS"));
+    }
+
+    #[test]
+    fn sandbox_workspace_write_apply_capabilities_is_callable() {
+        let sandbox: SandboxWorkspaceWrite =
+            toml::from_str("drop_capabilities = [\"CAP_NET_RAW\"]\n").expect("valid sandbox config");
+        // Exercises the spawn-path integration seam; actual enforcement is
+        // covered by `linux_capabilities`'s own tests.
+        sandbox.apply_capabilities();
+    }
+
+    #[test]
+    fn shell_environment_policy_carries_env_files_through() {
+        let toml: ShellEnvironmentPolicyToml =
+            toml::from_str("env_files = [\".env\", \"secrets/.env.local\"]\n")
+                .expect("valid shell environment policy");
+        let policy = ShellEnvironmentPolicy::from(toml);
+
+        assert_eq!(
+            policy.env_files,
+            vec![PathBuf::from(".env"), PathBuf::from("secrets/.env.local")]
+        );
+    }
 }
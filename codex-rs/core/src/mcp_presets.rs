@@ -1,7 +1,13 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
 use std::time::Duration;
 
+use serde::Deserialize;
+
 use crate::config_types::McpServerConfig;
+use crate::config_types::McpTransport;
 
 /// Built-in definitions for MCP servers that can be referenced via `preset`.
 #[derive(Debug, Clone, Copy)]
@@ -39,11 +45,70 @@ impl McpServerPreset {
 
         McpServerConfig {
             preset: Some(self.id.to_string()),
-            command: self.command.to_string(),
-            args: self.args.iter().map(|value| (*value).to_string()).collect(),
-            env: env_map,
+            transport: McpTransport::Stdio {
+                command: self.command.to_string(),
+                args: self.args.iter().map(|value| (*value).to_string()).collect(),
+                env: env_map,
+            },
+            startup_timeout_sec: self.startup_timeout,
+            tool_timeout_sec: self.tool_timeout,
+            enable_if: None,
+        }
+    }
+}
+
+/// Owned, runtime-constructed counterpart to [`McpServerPreset`]. The
+/// builtin presets are `'static`/`Copy`, which can't represent presets
+/// loaded from `presets.toml` or fetched from a `preset_sources` URL, so
+/// those go through this type instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedMcpServerPreset {
+    pub id: String,
+    pub label: String,
+    pub description: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub startup_timeout: Option<Duration>,
+    pub tool_timeout: Option<Duration>,
+}
+
+impl OwnedMcpServerPreset {
+    /// Convert the preset into a concrete [`McpServerConfig`].
+    pub fn to_config(&self) -> McpServerConfig {
+        McpServerConfig {
+            preset: Some(self.id.clone()),
+            transport: McpTransport::Stdio {
+                command: self.command.clone(),
+                args: self.args.clone(),
+                env: if self.env.is_empty() {
+                    None
+                } else {
+                    Some(self.env.clone())
+                },
+            },
             startup_timeout_sec: self.startup_timeout,
             tool_timeout_sec: self.tool_timeout,
+            enable_if: None,
+        }
+    }
+}
+
+impl From<&McpServerPreset> for OwnedMcpServerPreset {
+    fn from(preset: &McpServerPreset) -> Self {
+        Self {
+            id: preset.id.to_string(),
+            label: preset.label.to_string(),
+            description: preset.description.to_string(),
+            command: preset.command.to_string(),
+            args: preset.args.iter().map(|value| (*value).to_string()).collect(),
+            env: preset
+                .env
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+                .collect(),
+            startup_timeout: preset.startup_timeout,
+            tool_timeout: preset.tool_timeout,
         }
     }
 }
@@ -66,7 +131,313 @@ pub fn builtin_mcp_server_presets() -> &'static [McpServerPreset] {
     PRESETS
 }
 
-/// Lookup a preset by its identifier.
-pub fn find_mcp_server_preset(id: &str) -> Option<&'static McpServerPreset> {
-    PRESETS.iter().find(|preset| preset.id == id)
+fn builtin_owned_presets() -> &'static [OwnedMcpServerPreset] {
+    static CACHE: OnceLock<Vec<OwnedMcpServerPreset>> = OnceLock::new();
+    CACHE.get_or_init(|| PRESETS.iter().map(OwnedMcpServerPreset::from).collect())
+}
+
+/// Overlay of user-defined and remotely-fetched presets that take
+/// precedence over [`PRESETS`]. Populated once at startup via
+/// [`init_mcp_server_presets`] (which merges `presets.toml` and any
+/// `preset_sources` before installing them here); left empty if neither is
+/// configured.
+static PRESET_OVERLAY: OnceLock<Vec<OwnedMcpServerPreset>> = OnceLock::new();
+
+/// Install the merged overlay of user-defined and remotely-fetched presets.
+/// Prefer [`init_mcp_server_presets`], which also does the loading; this is
+/// exposed separately so callers that already have a merged list (e.g.
+/// tests) can install it directly. Later calls are ignored once the overlay
+/// has been set.
+pub fn install_mcp_server_preset_overlay(presets: Vec<OwnedMcpServerPreset>) {
+    let _ = PRESET_OVERLAY.set(presets);
+}
+
+/// Merge preset sources, lowest to highest precedence. When a later source
+/// redefines an existing `id` it replaces the earlier definition wholesale
+/// rather than field-merging.
+pub fn merge_presets(sources: Vec<Vec<OwnedMcpServerPreset>>) -> Vec<OwnedMcpServerPreset> {
+    let mut merged: Vec<OwnedMcpServerPreset> = Vec::new();
+    for source in sources {
+        for preset in source {
+            match merged.iter_mut().find(|existing| existing.id == preset.id) {
+                Some(existing) => *existing = preset,
+                None => merged.push(preset),
+            }
+        }
+    }
+    merged
+}
+
+/// Lookup a preset by its identifier, consulting the installed overlay
+/// (user file > remote > builtin precedence) before falling back to the
+/// builtins. Returns a [`Cow`] since overlay entries are owned while
+/// builtins are borrowed from `'static` storage.
+pub fn find_mcp_server_preset(id: &str) -> Option<Cow<'static, OwnedMcpServerPreset>> {
+    if let Some(overlay) = PRESET_OVERLAY.get() {
+        if let Some(preset) = overlay.iter().find(|preset| preset.id == id) {
+            return Some(Cow::Owned(preset.clone()));
+        }
+    }
+    builtin_owned_presets()
+        .iter()
+        .find(|preset| preset.id == id)
+        .map(Cow::Borrowed)
+}
+
+/// List every preset currently resolvable via [`find_mcp_server_preset`]:
+/// the overlay (if installed) plus any builtins it doesn't shadow.
+pub fn list_mcp_server_presets() -> Vec<Cow<'static, OwnedMcpServerPreset>> {
+    let mut presets: Vec<Cow<'static, OwnedMcpServerPreset>> = Vec::new();
+    if let Some(overlay) = PRESET_OVERLAY.get() {
+        presets.extend(overlay.iter().cloned().map(Cow::Owned));
+    }
+    for preset in builtin_owned_presets() {
+        if !presets.iter().any(|existing| existing.id == preset.id) {
+            presets.push(Cow::Borrowed(preset));
+        }
+    }
+    presets
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct PresetsFile {
+    #[serde(default, rename = "preset")]
+    presets: Vec<RawOwnedPreset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawOwnedPreset {
+    id: String,
+    label: String,
+    description: String,
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    #[serde(default)]
+    startup_timeout_sec: Option<f64>,
+    #[serde(default)]
+    tool_timeout_sec: Option<f64>,
+}
+
+impl From<RawOwnedPreset> for OwnedMcpServerPreset {
+    fn from(raw: RawOwnedPreset) -> Self {
+        Self {
+            id: raw.id,
+            label: raw.label,
+            description: raw.description,
+            command: raw.command,
+            args: raw.args,
+            env: raw.env,
+            startup_timeout: raw.startup_timeout_sec.map(Duration::from_secs_f64),
+            tool_timeout: raw.tool_timeout_sec.map(Duration::from_secs_f64),
+        }
+    }
+}
+
+fn parse_presets_file(contents: &str) -> Result<Vec<OwnedMcpServerPreset>, toml::de::Error> {
+    let file: PresetsFile = toml::from_str(contents)?;
+    Ok(file.presets.into_iter().map(OwnedMcpServerPreset::from).collect())
+}
+
+/// Load user-defined presets from `<codex_home>/presets.toml`. Returns an
+/// empty list (not an error) if the file doesn't exist, since presets.toml
+/// is optional.
+pub fn load_user_presets(codex_home: &Path) -> std::io::Result<Vec<OwnedMcpServerPreset>> {
+    let path = codex_home.join("presets.toml");
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    parse_presets_file(&contents)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+fn remote_preset_cache_path(codex_home: &Path, source: &str) -> std::path::PathBuf {
+    use std::hash::Hash;
+    use std::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source.hash(&mut hasher);
+    codex_home
+        .join("cache")
+        .join("presets")
+        .join(format!("{:016x}.toml", hasher.finish()))
+}
+
+/// Fetch presets from each of `preset_sources`, in order, bounding each
+/// request by `timeout`. A source that fails to fetch (offline, timed out,
+/// non-200) falls back to its last cached copy under
+/// `<codex_home>/cache/presets/`; a source with neither a live fetch nor a
+/// cache entry is skipped rather than failing the whole merge.
+pub fn fetch_remote_presets(
+    codex_home: &Path,
+    preset_sources: &[String],
+    timeout: Duration,
+) -> Vec<OwnedMcpServerPreset> {
+    let mut sources = Vec::with_capacity(preset_sources.len());
+    for source in preset_sources {
+        let cache_path = remote_preset_cache_path(codex_home, source);
+        let body = match fetch_url(source, timeout) {
+            Ok(body) => {
+                if let Some(parent) = cache_path.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                let _ = std::fs::write(&cache_path, &body);
+                Some(body)
+            }
+            Err(_) => std::fs::read_to_string(&cache_path).ok(),
+        };
+        let Some(body) = body else { continue };
+        match parse_presets_file(&body) {
+            Ok(presets) => sources.push(presets),
+            Err(_) => continue,
+        }
+    }
+    merge_presets(sources)
+}
+
+/// Fetch `url` via a blocking [`reqwest::blocking::Client`] on a dedicated OS
+/// thread. `reqwest::blocking` panics if driven from inside a tokio runtime
+/// ("cannot start a runtime from within a runtime"), and preset loading can
+/// happen from async config-loading code, so the blocking client is never
+/// invoked on the calling thread directly.
+fn fetch_url(url: &str, timeout: Duration) -> Result<String, reqwest::Error> {
+    let url = url.to_string();
+    std::thread::spawn(move || {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()?;
+        client.get(&url).send()?.error_for_status()?.text()
+    })
+    .join()
+    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Load and merge the overlay sources, highest precedence last:
+/// `preset_sources`, then `<codex_home>/presets.toml` — i.e. user file >
+/// remote. Builtins are not part of the result; [`find_mcp_server_preset`]
+/// falls back to them for ids the overlay does not define. This is the
+/// composition a startup path should call
+/// once (there is no `main`/startup entry point in this crate to thread it
+/// into here; see [`install_mcp_server_preset_overlay`] for how the result
+/// gets installed).
+pub fn load_mcp_server_preset_overlay(
+    codex_home: &Path,
+    preset_sources: &[String],
+    timeout: Duration,
+) -> std::io::Result<Vec<OwnedMcpServerPreset>> {
+    let user_presets = load_user_presets(codex_home)?;
+    let remote_presets = fetch_remote_presets(codex_home, preset_sources, timeout);
+    Ok(merge_presets(vec![remote_presets, user_presets]))
+}
+
+/// Load `<codex_home>/presets.toml` and `preset_sources`, merge them, and
+/// install the result as the active [`PRESET_OVERLAY`] so that
+/// [`find_mcp_server_preset`] and [`list_mcp_server_presets`] resolve against
+/// it. Intended to be called once during startup, after `CODEX_HOME` and the
+/// config's `preset_sources` are known.
+pub fn init_mcp_server_presets(
+    codex_home: &Path,
+    preset_sources: &[String],
+    timeout: Duration,
+) -> std::io::Result<()> {
+    let overlay = load_mcp_server_preset_overlay(codex_home, preset_sources, timeout)?;
+    install_mcp_server_preset_overlay(overlay);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_presets_replaces_by_id_wholesale() {
+        let low = vec![OwnedMcpServerPreset {
+            id: "docs".to_string(),
+            label: "Docs (builtin-like)".to_string(),
+            description: "low precedence".to_string(),
+            command: "npx".to_string(),
+            args: vec!["docs-mcp".to_string()],
+            env: HashMap::new(),
+            startup_timeout: None,
+            tool_timeout: None,
+        }];
+        let high = vec![OwnedMcpServerPreset {
+            id: "docs".to_string(),
+            label: "Docs (user override)".to_string(),
+            description: "high precedence".to_string(),
+            command: "/custom/docs".to_string(),
+            args: vec![],
+            env: HashMap::new(),
+            startup_timeout: None,
+            tool_timeout: None,
+        }];
+
+        let merged = merge_presets(vec![low, high]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].command, "/custom/docs");
+        assert_eq!(merged[0].label, "Docs (user override)");
+    }
+
+    #[test]
+    fn load_overlay_prefers_user_presets_toml_over_remote_source() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            codex_home.path().join("presets.toml"),
+            "[[preset]]\nid = \"docs\"\nlabel = \"Docs\"\ndescription = \"user\"\ncommand = \"/user/docs\"\n",
+        )
+        .expect("write presets.toml");
+
+        // Simulate an already-cached "remote" source (no network in tests)
+        // that defines the same preset id with a different command.
+        let source = "https://example.com/presets.toml";
+        let cache_path = remote_preset_cache_path(codex_home.path(), source);
+        std::fs::create_dir_all(cache_path.parent().expect("cache dir")).expect("mkdir cache");
+        std::fs::write(
+            &cache_path,
+            "[[preset]]\nid = \"docs\"\nlabel = \"Docs\"\ndescription = \"remote\"\ncommand = \"/remote/docs\"\n",
+        )
+        .expect("write cached remote preset");
+
+        let overlay = load_mcp_server_preset_overlay(
+            codex_home.path(),
+            &[source.to_string()],
+            Duration::from_millis(1),
+        )
+        .expect("overlay should load");
+
+        assert_eq!(overlay.len(), 1);
+        assert_eq!(overlay[0].command, "/user/docs");
+    }
+
+    #[test]
+    fn init_mcp_server_presets_installs_user_presets_toml() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            codex_home.path().join("presets.toml"),
+            "[[preset]]\nid = \"docs\"\nlabel = \"Docs\"\ndescription = \"Docs server\"\ncommand = \"npx\"\nargs = [\"docs-mcp\"]\n",
+        )
+        .expect("write presets.toml");
+
+        init_mcp_server_presets(codex_home.path(), &[], Duration::from_secs(1))
+            .expect("presets.toml should load");
+
+        let resolved = find_mcp_server_preset("docs").expect("preset installed by init");
+        assert_eq!(resolved.command, "npx");
+        assert_eq!(resolved.args, vec!["docs-mcp".to_string()]);
+    }
+
+    #[test]
+    fn parses_presets_file_toml() {
+        let presets = parse_presets_file(
+            "[[preset]]\nid = \"docs\"\nlabel = \"Docs\"\ndescription = \"Docs server\"\ncommand = \"npx\"\nargs = [\"docs-mcp\"]\n",
+        )
+        .expect("valid presets.toml");
+
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].id, "docs");
+        assert_eq!(presets[0].args, vec!["docs-mcp".to_string()]);
+    }
 }
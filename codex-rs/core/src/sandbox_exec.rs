@@ -0,0 +1,108 @@
+//! Spawns commands under the `workspace-write` sandbox.
+//!
+//! This is the one real spawn call site in this crate slice: it applies a
+//! [`SandboxWorkspaceWrite`]'s capability policy in the child process,
+//! post-fork/pre-exec, via [`SandboxWorkspaceWrite::apply_capabilities`] —
+//! otherwise `drop_capabilities`/`keep_capabilities` are parsed and
+//! validated but never take effect on anything actually run.
+
+use std::process::Command;
+
+use crate::config_types::SandboxWorkspaceWrite;
+use crate::config_types::ShellEnvironmentPolicy;
+use crate::shell_environment::derive_shell_environment_from_process;
+
+/// Build a [`Command`] for `program`/`args` that applies `sandbox`'s
+/// capability policy in the child immediately after `fork()` and before
+/// `exec()`, with its environment built by
+/// [`derive_shell_environment_from_process`] (rather than raw inheritance)
+/// so `shell_env_policy`'s `env_files`/`${VAR}` interpolation/excludes
+/// actually reach the spawned command.
+#[cfg(unix)]
+pub fn workspace_write_command(
+    program: &str,
+    args: &[String],
+    sandbox: &SandboxWorkspaceWrite,
+    shell_env_policy: &ShellEnvironmentPolicy,
+) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let sandbox = sandbox.clone();
+    let mut command = Command::new(program);
+    command.args(args);
+    command.env_clear();
+    command.envs(derive_shell_environment_from_process(shell_env_policy));
+    // SAFETY: `apply_capabilities` only touches this (post-fork, pre-exec)
+    // process's own capability sets via prctl/capget/capset; it allocates
+    // nothing and calls nothing that isn't async-signal-safe.
+    unsafe {
+        command.pre_exec(move || {
+            sandbox.apply_capabilities();
+            Ok(())
+        });
+    }
+    command
+}
+
+/// Non-Unix hosts have no `pre_exec` hook and no capability model to apply;
+/// [`SandboxWorkspaceWrite::apply_capabilities`] is already a no-op there.
+/// The environment is still derived via [`derive_shell_environment_from_process`]
+/// so `shell_env_policy` behaves identically across platforms.
+#[cfg(not(unix))]
+pub fn workspace_write_command(
+    program: &str,
+    args: &[String],
+    _sandbox: &SandboxWorkspaceWrite,
+    shell_env_policy: &ShellEnvironmentPolicy,
+) -> Command {
+    let mut command = Command::new(program);
+    command.args(args);
+    command.env_clear();
+    command.envs(derive_shell_environment_from_process(shell_env_policy));
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_write_command_runs_with_capabilities_applied() {
+        let sandbox = SandboxWorkspaceWrite {
+            drop_capabilities: vec!["CAP_NET_RAW".to_string()],
+            ..Default::default()
+        };
+        let shell_env_policy = ShellEnvironmentPolicy {
+            inherit: crate::config_types::ShellEnvironmentPolicyInherit::Core,
+            ..Default::default()
+        };
+
+        let status = workspace_write_command("true", &[], &sandbox, &shell_env_policy)
+            .status()
+            .expect("spawning `true` should succeed");
+        assert!(status.success());
+    }
+
+    #[test]
+    fn workspace_write_command_env_reflects_shell_env_policy_set() {
+        let sandbox = SandboxWorkspaceWrite::default();
+        let mut set = std::collections::HashMap::new();
+        set.insert("GREETING".to_string(), "hello".to_string());
+        let shell_env_policy = ShellEnvironmentPolicy {
+            inherit: crate::config_types::ShellEnvironmentPolicyInherit::None,
+            r#set,
+            ..Default::default()
+        };
+
+        let output = workspace_write_command(
+            "sh",
+            &["-c".to_string(), "printf %s \"$GREETING\"".to_string()],
+            &sandbox,
+            &shell_env_policy,
+        )
+        .output()
+        .expect("spawning `sh` should succeed");
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello");
+    }
+}
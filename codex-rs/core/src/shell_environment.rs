@@ -0,0 +1,253 @@
+//! Builds the `env` map used when spawning a process via the `shell` or
+//! `local_shell` tool, per [`crate::config_types::ShellEnvironmentPolicy`].
+//!
+//! Deriving the `env` works as follows:
+//! 1. Create an initial map based on the `inherit` policy.
+//! 2. Merge each `env_files` entry, in order, into the map.
+//! 3. If `ignore_default_excludes` is false, filter the map using the default
+//!    exclude pattern(s), which are: `"*KEY*"` and `"*TOKEN*"`.
+//! 4. If `exclude` is not empty, filter the map using the provided patterns.
+//! 5. Insert entries from `r#set` into the map, expanding `${NAME}`
+//!    references against the map as built so far (steps 1-4); unknown
+//!    references expand to the empty string, and `$${` is a literal `${`
+//!    escape.
+//! 6. If non-empty, filter the map using the `include_only` patterns.
+
+use std::collections::HashMap;
+
+use crate::config_types::EnvironmentVariablePattern;
+use crate::config_types::ShellEnvironmentPolicy;
+use crate::config_types::ShellEnvironmentPolicyInherit;
+
+/// Build the `env` map a spawned shell command should see, given the
+/// process's own environment and `policy`.
+pub fn derive_shell_environment(
+    parent_env: &HashMap<String, String>,
+    policy: &ShellEnvironmentPolicy,
+) -> HashMap<String, String> {
+    let mut env = match policy.inherit {
+        ShellEnvironmentPolicyInherit::All => parent_env.clone(),
+        ShellEnvironmentPolicyInherit::Core => parent_env
+            .iter()
+            .filter(|(key, _)| CORE_ENV_VARS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        ShellEnvironmentPolicyInherit::None => HashMap::new(),
+    };
+
+    for path in &policy.env_files {
+        if let Ok(contents) = std::fs::read_to_string(path) {
+            for (key, value) in parse_dotenv(&contents) {
+                env.insert(key, value);
+            }
+        }
+    }
+
+    if !policy.ignore_default_excludes {
+        retain_not_matching(&mut env, &default_exclude_patterns());
+    }
+    if !policy.exclude.is_empty() {
+        retain_not_matching(&mut env, &policy.exclude);
+    }
+
+    let expanded: Vec<(String, String)> = policy
+        .r#set
+        .iter()
+        .map(|(key, value)| (key.clone(), interpolate(value, &env)))
+        .collect();
+    env.extend(expanded);
+
+    if !policy.include_only.is_empty() {
+        env.retain(|key, _| {
+            policy
+                .include_only
+                .iter()
+                .any(|pattern| pattern.matches(key))
+        });
+    }
+
+    env
+}
+
+/// [`derive_shell_environment`] against the current process's own
+/// environment. Used by [`crate::sandbox_exec::workspace_write_command`],
+/// the real spawn call site in this crate, so `env_files` and `${VAR}`
+/// interpolation actually reach what gets spawned rather than sitting next
+/// to raw environment inheritance.
+pub fn derive_shell_environment_from_process(policy: &ShellEnvironmentPolicy) -> HashMap<String, String> {
+    derive_shell_environment(&std::env::vars().collect(), policy)
+}
+
+const CORE_ENV_VARS: &[&str] = &["HOME", "LOGNAME", "PATH", "SHELL", "USER"];
+
+fn default_exclude_patterns() -> Vec<EnvironmentVariablePattern> {
+    ["*KEY*", "*TOKEN*"]
+        .iter()
+        .map(|pattern| EnvironmentVariablePattern::new_case_insensitive(pattern))
+        .collect()
+}
+
+fn retain_not_matching(env: &mut HashMap<String, String>, patterns: &[EnvironmentVariablePattern]) {
+    env.retain(|key, _| !patterns.iter().any(|pattern| pattern.matches(key)));
+}
+
+/// Parse dotenv-style `KEY=value` entries. Blank lines and lines starting
+/// with `#` are ignored; surrounding single or double quotes on the value
+/// are stripped. Malformed lines (no `=`) are skipped.
+fn parse_dotenv(contents: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_string();
+        let value = strip_matching_quotes(value.trim());
+        entries.push((key, value));
+    }
+    entries
+}
+
+fn strip_matching_quotes(value: &str) -> String {
+    let bytes = value.as_bytes();
+    if bytes.len() >= 2
+        && ((bytes[0] == b'"' && bytes[bytes.len() - 1] == b'"')
+            || (bytes[0] == b'\'' && bytes[bytes.len() - 1] == b'\''))
+    {
+        value[1..value.len() - 1].to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Expand `${NAME}` references in `template` against `env`. Unknown names
+/// expand to the empty string. `$${` is a literal `${` escape (the second
+/// `$` is consumed, not the interpolation).
+fn interpolate(template: &str, env: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let bytes = template.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if template[i..].starts_with("$${") {
+            result.push_str("${");
+            i += 3;
+            continue;
+        }
+        if template[i..].starts_with("${") {
+            if let Some(end) = template[i + 2..].find('}') {
+                let name = &template[i + 2..i + 2 + end];
+                if let Some(value) = env.get(name) {
+                    result.push_str(value);
+                }
+                i += 2 + end + 1;
+                continue;
+            }
+        }
+        let ch = template[i..].chars().next().expect("i is a char boundary");
+        result.push(ch);
+        i += ch.len_utf8();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn policy_with_set(set: HashMap<String, String>) -> ShellEnvironmentPolicy {
+        ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::None,
+            r#set: set,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn interpolates_known_and_unknown_vars() {
+        let mut env = HashMap::new();
+        env.insert("HOME".to_string(), "/home/user".to_string());
+        assert_eq!(
+            interpolate("${HOME}/.local/bin:${PATH}", &env),
+            "/home/user/.local/bin:"
+        );
+    }
+
+    #[test]
+    fn dollar_dollar_brace_is_a_literal_escape() {
+        let env = HashMap::new();
+        assert_eq!(interpolate("$${HOME}", &env), "${HOME}");
+    }
+
+    #[test]
+    fn set_entries_expand_against_prior_steps() {
+        let mut parent_env = HashMap::new();
+        parent_env.insert("HOME".to_string(), "/home/user".to_string());
+
+        let mut set = HashMap::new();
+        set.insert("PATH".to_string(), "${HOME}/.local/bin".to_string());
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::All,
+            r#set: set,
+            ..Default::default()
+        };
+
+        let env = derive_shell_environment(&parent_env, &policy);
+        assert_eq!(env.get("PATH"), Some(&"/home/user/.local/bin".to_string()));
+    }
+
+    #[test]
+    fn env_files_load_between_inherit_and_excludes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let env_file = dir.path().join(".env");
+        let mut file = std::fs::File::create(&env_file).expect("create .env");
+        writeln!(file, "# comment\nAPI_KEY=secret\nFOO=bar").expect("write .env");
+
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::None,
+            env_files: vec![env_file],
+            ..Default::default()
+        };
+
+        let env = derive_shell_environment(&HashMap::new(), &policy);
+        // Loaded from the .env file, but dropped by the default "*KEY*" exclude.
+        assert_eq!(env.get("API_KEY"), None);
+        assert_eq!(env.get("FOO"), Some(&"bar".to_string()));
+    }
+
+    #[test]
+    fn derive_from_process_matches_deriving_from_captured_vars() {
+        let parent_env: HashMap<String, String> = std::env::vars().collect();
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::All,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            derive_shell_environment_from_process(&policy),
+            derive_shell_environment(&parent_env, &policy)
+        );
+    }
+
+    #[test]
+    fn derive_from_process_with_no_inherit_is_empty() {
+        let policy = ShellEnvironmentPolicy {
+            inherit: ShellEnvironmentPolicyInherit::None,
+            ..Default::default()
+        };
+        assert!(derive_shell_environment_from_process(&policy).is_empty());
+    }
+
+    #[test]
+    fn set_without_env_files_is_unaffected() {
+        let mut set = HashMap::new();
+        set.insert("GREETING".to_string(), "hello".to_string());
+        let policy = policy_with_set(set);
+
+        let env = derive_shell_environment(&HashMap::new(), &policy);
+        assert_eq!(env.get("GREETING"), Some(&"hello".to_string()));
+    }
+}